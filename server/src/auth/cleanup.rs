@@ -0,0 +1,84 @@
+use crate::db::Database;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// default policy for how long a refresh token can go un-rotated before the
+// sweeper treats it as stale, regardless of `used`; mirrors the session
+// lifetime granted by `refresh`. Callers that want a different policy pass
+// their own `max_age` to `spawn_sweeper` instead of relying on this.
+pub const DEFAULT_REFRESH_TOKEN_MAX_AGE: Duration = Duration::from_secs(24 * 7 * 3600);
+
+// how often the sweeper wakes up and runs a pass
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// spawn a task that runs for the lifetime of the process, periodically
+// deleting expired sessions and refresh tokens older than `max_age` so the
+// two auth tables stay bounded without depending on login/refresh traffic
+pub fn spawn_sweeper(database: Database, interval: Duration, max_age: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(error) = sweep_once(&database, max_age).await {
+                eprintln!("session sweep failed: {}", error);
+            }
+        }
+    })
+}
+
+async fn sweep_once(database: &Database, max_age: Duration) -> Result<(), sqlx::Error> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    sqlx::query!("DELETE FROM session WHERE expire < ?", now)
+        .execute(&database.pool)
+        .await?;
+
+    let max_age_cutoff = now.saturating_sub(max_age.as_secs());
+    sqlx::query!("DELETE FROM refresh_token WHERE issued_at < ?", max_age_cutoff)
+        .execute(&database.pool)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_support::test_database;
+
+    #[tokio::test]
+    async fn sweep_once_removes_expired_session_and_stale_refresh_token() {
+        let database = test_database().await;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        sqlx::query!(
+            "INSERT INTO session (id, session, expire) VALUES (1, 'cleanup-test-session', ?)",
+            now - 1,
+        )
+            .execute(&database.pool)
+            .await
+            .unwrap();
+        let max_age = DEFAULT_REFRESH_TOKEN_MAX_AGE;
+        sqlx::query!(
+            "INSERT INTO refresh_token (token, id, family_id, used, issued_at) VALUES ('cleanup-test-token', 1, 'cleanup-test-family', false, ?)",
+            now - max_age.as_secs() - 1,
+        )
+            .execute(&database.pool)
+            .await
+            .unwrap();
+
+        sweep_once(&database, max_age).await.unwrap();
+
+        let session = sqlx::query!("SELECT session FROM session WHERE session = 'cleanup-test-session'")
+            .fetch_optional(&database.pool)
+            .await
+            .unwrap();
+        assert!(session.is_none());
+
+        let refresh_token = sqlx::query!("SELECT token FROM refresh_token WHERE token = 'cleanup-test-token'")
+            .fetch_optional(&database.pool)
+            .await
+            .unwrap();
+        assert!(refresh_token.is_none());
+    }
+}
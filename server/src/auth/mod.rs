@@ -0,0 +1,15 @@
+pub mod cleanup;
+pub mod password;
+pub mod session;
+
+// shared fixtures for the `#[cfg(test)]` modules under `auth::`, so each one
+// doesn't paste its own copy of the same database setup
+#[cfg(test)]
+pub(crate) mod test_support {
+    use crate::db::Database;
+
+    pub async fn test_database() -> Database {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for db tests");
+        Database::connect(&url).await.expect("failed to connect to test database")
+    }
+}
@@ -0,0 +1,94 @@
+use crate::db::Database;
+use crate::routes::ApiError;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use warp::Filter;
+
+// identity of the caller behind a validated session, passed on to handlers
+#[derive(Clone, Copy, Debug)]
+pub struct AuthContext {
+    pub id: u64,
+}
+
+pub(crate) fn token_from_header_or_cookie(
+    header: Option<String>,
+    cookie: Option<String>,
+) -> Option<String> {
+    if let Some(header) = header {
+        if let Some(token) = header.strip_prefix("Bearer ") {
+            return Some(token.to_string());
+        }
+    }
+    cookie
+}
+
+// warp filter that extracts an `AuthContext` from a live session, looked up
+// from an `Authorization: Bearer` header or a `session` cookie. Rejects with
+// `ApiError::NotAuthorized` when the token doesn't match a session, and with
+// `ApiError::SessionExpired` (deleting the row) when it has expired.
+pub fn with_session(
+    database: Database,
+) -> impl Filter<Extract = (AuthContext,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::cookie::optional::<String>("session"))
+        .and_then(move |header: Option<String>, cookie: Option<String>| {
+            let database = database.clone();
+            async move {
+                let session = match token_from_header_or_cookie(header, cookie) {
+                    Some(session) => session,
+                    None => return Err(warp::reject::custom(ApiError::NotAuthorized)),
+                };
+
+                let row = sqlx::query!("SELECT id, expire FROM session WHERE session = ?", session)
+                    .fetch_optional(&database.pool)
+                    .await
+                    .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+                let row = match row {
+                    Some(row) => row,
+                    None => return Err(warp::reject::custom(ApiError::NotAuthorized)),
+                };
+
+                let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                if current_time > row.expire {
+                    sqlx::query!("DELETE FROM session WHERE session = ?", session)
+                        .execute(&database.pool)
+                        .await
+                        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+                    return Err(warp::reject::custom(ApiError::SessionExpired));
+                }
+
+                Ok(AuthContext { id: row.id })
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::test_support::test_database;
+    use crate::utils;
+
+    #[tokio::test]
+    async fn expired_session_is_rejected_and_deleted() {
+        let database = test_database().await;
+        let session = utils::hash_from_u8(b"session-filter-expiry-test".to_vec());
+        let expire = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() - 1;
+        sqlx::query!("INSERT INTO session (id, session, expire) VALUES (1, ?, ?)", session, expire)
+            .execute(&database.pool)
+            .await
+            .unwrap();
+
+        let filter = with_session(database.clone());
+        let result = warp::test::request()
+            .header("authorization", format!("Bearer {}", session))
+            .filter(&filter)
+            .await;
+        assert!(result.is_err());
+
+        let remaining = sqlx::query!("SELECT session FROM session WHERE session = ?", session)
+            .fetch_optional(&database.pool)
+            .await
+            .unwrap();
+        assert!(remaining.is_none(), "expired session row should have been deleted");
+    }
+}
@@ -0,0 +1,14 @@
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::MySqlPool;
+
+#[derive(Clone)]
+pub struct Database {
+    pub pool: MySqlPool,
+}
+
+impl Database {
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let pool = MySqlPoolOptions::new().connect(url).await?;
+        Ok(Database { pool })
+    }
+}
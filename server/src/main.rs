@@ -0,0 +1,50 @@
+mod auth;
+mod db;
+mod routes;
+mod utils;
+
+use db::Database;
+use routes::handlers::auth as auth_handlers;
+use warp::Filter;
+
+fn with_database(database: Database) -> impl Filter<Extract = (Database,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || database.clone())
+}
+
+#[tokio::main]
+async fn main() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let database = Database::connect(&database_url).await.expect("failed to connect to database");
+
+    // keep the session/refresh_token tables bounded for the lifetime of the
+    // process, independent of login/refresh traffic
+    auth::cleanup::spawn_sweeper(
+        database.clone(),
+        auth::cleanup::DEFAULT_SWEEP_INTERVAL,
+        auth::cleanup::DEFAULT_REFRESH_TOKEN_MAX_AGE,
+    );
+
+    let login = warp::path!("auth" / "login")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_database(database.clone()))
+        .and_then(auth_handlers::login);
+
+    let refresh = warp::path!("auth" / "refresh")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_database(database.clone()))
+        .and_then(auth_handlers::refresh);
+
+    let logout = warp::path!("auth" / "logout")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::cookie::optional::<String>("session"))
+        .and(with_database(database.clone()))
+        .and_then(auth_handlers::logout);
+
+    let routes = login.or(refresh).or(logout);
+
+    let port: u16 = std::env::var("PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(3030);
+    warp::serve(routes).run(([127, 0, 0, 1], port)).await;
+}
@@ -1,11 +1,14 @@
+use crate::auth::password::{self, CredentialParams};
+use crate::auth::session::token_from_header_or_cookie;
 use crate::db::Database;
 use crate::routes::ApiError;
 use crate::routes::*;
 use crate::utils;
 
-use mysql::{params, prelude::Queryable, Row};
+use argon2::password_hash::PasswordHash;
 use rand_core::{RngCore, OsRng};
 use warp::reject::Rejection;
+use warp::reply::{Reply, Response};
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::Serialize;
 
@@ -20,100 +23,248 @@ pub struct ResponseData {
     refresh_token: String,
 }
 
+// JSON body stays the source of truth for clients that store tokens
+// themselves; browser-style clients can instead rely on this cookie, which
+// is not readable from JS and is only ever sent back to this origin
+fn session_cookie(session: &str, max_age_sec: u64) -> String {
+    format!(
+        "session={}; HttpOnly; Secure; SameSite=Strict; Max-Age={}; Path=/",
+        session, max_age_sec,
+    )
+}
+
+fn cleared_session_cookie() -> String {
+    session_cookie("", 0)
+}
+
+fn with_session_cookie(response: ResponseData, cookie: String) -> Response {
+    warp::reply::with_header(warp::reply::json(&response), "Set-Cookie", cookie).into_response()
+}
+
 pub async fn login(json_data: LoginData, database: Database) -> Result<impl warp::Reply, Rejection> {
     let username: String = json_data.clone().username;
     let pw = json_data.pw;
 
     // get salt and pw from login table
-    let mut conn = database.pool.get_conn().unwrap();
-    let result: Vec<Row> = conn.exec("SELECT id, salt, pw FROM login WHERE username = :username", params! {"username" => username.clone()}).unwrap();
-    if result.len() == 0 {
-        return Err(warp::reject::custom(ApiError::NotAuthorized))
-    }
+    let row = sqlx::query!("SELECT id, salt, pw FROM login WHERE username = ?", username)
+        .fetch_optional(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+    let row = match row {
+        Some(row) => row,
+        None => return Err(warp::reject::custom(ApiError::NotAuthorized)),
+    };
+    let (id, salt, db_pw) = (row.id, row.salt, row.pw);
 
-    // check if user pw is correct
-    let (id, salt, db_pw): (u64, String, String) = mysql::from_row(result[0].clone());
-    let hashed_pw = utils::hash_from_string(format!("{}{}", pw, salt));
-    if hashed_pw != db_pw {
-        return Err(warp::reject::custom(ApiError::NotAuthorized))
+    // check if user pw is correct. `pw` column holds an Argon2id PHC string
+    // for every account created after the migration; accounts still on the
+    // legacy salted hash are verified against that scheme instead and then
+    // silently upgraded in place.
+    match PasswordHash::new(&db_pw) {
+        Ok(_) => {
+            if !password::verify_password(&pw, &db_pw) {
+                return Err(warp::reject::custom(ApiError::NotAuthorized))
+            }
+        }
+        Err(_) => {
+            let hashed_pw = utils::hash_from_string(format!("{}{}", pw, salt));
+            if hashed_pw != db_pw {
+                return Err(warp::reject::custom(ApiError::NotAuthorized))
+            }
+            // legacy match succeeded; rehash with Argon2id so future logins
+            // skip this branch entirely
+            let rehashed = password::hash_password(&pw, CredentialParams::default());
+            sqlx::query!("UPDATE login SET pw = ? WHERE id = ?", rehashed, id)
+                .execute(&database.pool)
+                .await
+                .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+        }
     }
 
     // check if user has expired session
-    let result: Vec<Row> = conn.exec("SELECT session, expire FROM session WHERE id = :id", params! {"id" => id.clone()}).unwrap();
-    if result.len() > 0 {
-        for row in result {
-            let (session, expire): (String, u64) = mysql::from_row(row);
-            let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            if current_time > expire {
-                // delete expired session
-                let _result: Vec<Row> = conn.exec("DELETE FROM session WHERE session = :session", params! {"session" => session}).unwrap();
-            }
+    let expired_sessions = sqlx::query!("SELECT session, expire FROM session WHERE id = ?", id)
+        .fetch_all(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+    let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    for row in expired_sessions {
+        if current_time > row.expire {
+            // delete expired session
+            sqlx::query!("DELETE FROM session WHERE session = ?", row.session)
+                .execute(&database.pool)
+                .await
+                .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
         }
     }
 
+    // start a fresh token family for this login; the session carries the
+    // same family_id so logout can scope its refresh-token revocation to
+    // just this login instead of every device the user is signed in on
+    let family_id = utils::hash_from_u8(OsRng.next_u64().to_le_bytes().to_vec());
+
     // make session by hashing random number and id
-    let mut key = OsRng.next_u64().to_le_bytes().to_vec();
-    let mut session_source = id.clone().to_string().into_bytes();
-    session_source.append(&mut key);
-    let session = utils::hash_from_u8(session_source);
-    // make expire time
-    let expire = match json_data.remember {
-        true => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60*60*SESSION_REMEMBER_EXPIRE_HOUR,
-        false => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 60*60*SESSION_NO_REMEMBER_EXPIRE_MINUTE,
+    let session = make_random_token(id);
+    // make expire time; `remember` also controls the cookie's Max-Age below
+    let max_age_sec = match json_data.remember {
+        true => 60*60*SESSION_REMEMBER_EXPIRE_HOUR,
+        false => 60*SESSION_NO_REMEMBER_EXPIRE_MINUTE,
     };
+    let expire = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + max_age_sec;
     // insert session to the session table
-    let _result: Vec<Row> = conn.exec("INSERT INTO session (id, session, expire) VALUES (:id, :session, :expire)", params! {"id" => id.clone(), "session" => session.clone(), "expire" => expire}).unwrap();
+    sqlx::query!(
+        "INSERT INTO session (id, session, expire, family_id) VALUES (?, ?, ?, ?)",
+        id, session, expire, family_id,
+    )
+        .execute(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
 
-    // make refresh_toke by hashing random number and id
-    let mut key = OsRng.next_u64().to_le_bytes().to_vec();
-    let mut refresh_token_source = id.clone().to_string().into_bytes();
-    refresh_token_source.append(&mut key);
-    let refresh_token = utils::hash_from_u8(refresh_token_source);
-    
-    // insert refresh_token to the login table
-    let _result: Vec<Row> = conn.exec("UPDATE login SET refresh_token = :refresh_token WHERE id = :id", params! {"refresh_token" => refresh_token.clone(), "id" => id}).unwrap();
-
-    // response
+    // issue the family's first token
+    let refresh_token = make_random_token(id);
+    let issued_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    sqlx::query!(
+        "INSERT INTO refresh_token (token, id, family_id, used, issued_at) VALUES (?, ?, ?, false, ?)",
+        refresh_token, id, family_id, issued_at,
+    )
+        .execute(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+
+    // response; the cookie is opt-in transport for browser-style clients,
+    // the JSON body remains authoritative for everyone else
     let response = ResponseData {
-        session: session,
+        session: session.clone(),
         refresh_token: refresh_token,
     };
 
-    return Ok(warp::reply::json(&response));
+    return Ok(with_session_cookie(response, session_cookie(&session, max_age_sec)));
+}
+
+// make a random token (used for both sessions and refresh tokens) by
+// hashing a random number and the user id
+fn make_random_token(id: u64) -> String {
+    let mut key = OsRng.next_u64().to_le_bytes().to_vec();
+    let mut token_source = id.to_string().into_bytes();
+    token_source.append(&mut key);
+    utils::hash_from_u8(token_source)
 }
 
 pub async fn refresh(json_data: RefreshData, database: Database) -> Result<impl warp::Reply, Rejection> {
-    // check if the refresh token is valid
+    // look up the presented token; rotation-with-reuse-detection lives on
+    // top of this lookup rather than the single `login.refresh_token`
+    // column so a stolen-and-replayed token can be told apart from the
+    // legitimate one
     let refresh_token = json_data.refresh_token;
-    let mut conn = database.pool.get_conn().unwrap();
-    let result: Vec<Row> = conn.exec("SELECT id FROM login WHERE refresh_token = :refresh_token", params! {"refresh_token" => refresh_token}).unwrap();
-    if result.len() == 0 {
+    let row = sqlx::query!(
+        "SELECT id, family_id, used as `used: bool` FROM refresh_token WHERE token = ?",
+        refresh_token,
+    )
+        .fetch_optional(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+    let row = match row {
+        Some(row) => row,
+        None => return Err(warp::reject::custom(ApiError::NotAuthorized)),
+    };
+    let (id, family_id, used) = (row.id, row.family_id, row.used);
+
+    // atomically claim the token: `used` only flips 0 -> 1 once, so two
+    // concurrent refreshes racing the same unused token can't both win this
+    // update. Either `used` was already true, or we lost the race to
+    // another request between the SELECT above and this UPDATE — both look
+    // identical to a replay from here on and get the same response.
+    let claim = sqlx::query!(
+        "UPDATE refresh_token SET used = true WHERE token = ? AND used = false",
+        refresh_token,
+    )
+        .execute(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+
+    if used || claim.rows_affected() == 0 {
+        // replay of an already-rotated token (or a lost race for one that
+        // wasn't): treat the whole family as compromised and log out both
+        // the attacker and the victim, but scope the damage to this family
+        // rather than every session/token the user holds on other devices
+        sqlx::query!("DELETE FROM session WHERE family_id = ?", family_id)
+            .execute(&database.pool)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+        sqlx::query!("DELETE FROM refresh_token WHERE family_id = ?", family_id)
+            .execute(&database.pool)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
         return Err(warp::reject::custom(ApiError::NotAuthorized))
     }
 
+    // mint the next token in the family
+    let next_refresh_token = make_random_token(id);
+    let issued_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    sqlx::query!(
+        "INSERT INTO refresh_token (token, id, family_id, used, issued_at) VALUES (?, ?, ?, false, ?)",
+        next_refresh_token, id, family_id, issued_at,
+    )
+        .execute(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+
     // make session by hashing random number and id
-    let mut key = OsRng.next_u64().to_le_bytes().to_vec();
-    let id: String = mysql::from_row(result[0].clone());
-    let mut session_source = id.clone().into_bytes();
-    session_source.append(&mut key);
-    let session = utils::hash_from_u8(session_source);
+    let session = make_random_token(id);
     // make expire
     let expire = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + REFRESHED_SESSION_EXPIRE_HOUR * 3600;
-    // insert new session to session table
-    let _result: Vec<Row> = conn.exec("INSERT INTO session (id, session, expire) VALUES (:id, :session, :expire)", params! {"id" => id.clone(), "session" => session.clone(), "expire" => expire}).unwrap();
-
-    // update used refresh token to new refresh token
-    let mut key = OsRng.next_u64().to_le_bytes().to_vec();
-    let mut refresh_token_source = id.clone().into_bytes();
-    refresh_token_source.append(&mut key);
-    let refresh_token = utils::hash_from_u8(refresh_token_source);
-    
-    let _result: Vec<Row> = conn.exec("UPDATE login SET refresh_token = :refresh_token WHERE id = :id", params! {"refresh_token" => refresh_token.clone(), "id" => id}).unwrap();
+    // insert new session to session table, carrying forward the same
+    // family_id so it stays scoped to this login's refresh-token family
+    sqlx::query!(
+        "INSERT INTO session (id, session, expire, family_id) VALUES (?, ?, ?, ?)",
+        id, session, expire, family_id,
+    )
+        .execute(&database.pool)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
 
     // reponse
     let response = ResponseData {
-        session: session,
-        refresh_token: refresh_token,
+        session: session.clone(),
+        refresh_token: next_refresh_token,
     };
-    return Ok(warp::reply::json(&response));
+    let max_age_sec = REFRESHED_SESSION_EXPIRE_HOUR * 3600;
+    return Ok(with_session_cookie(response, session_cookie(&session, max_age_sec)));
+}
+
+pub async fn logout(
+    header: Option<String>,
+    cookie: Option<String>,
+    database: Database,
+) -> Result<impl warp::Reply, Rejection> {
+    // logging out is idempotent: with no session to identify, there is
+    // nothing to delete, but the client still gets its cookie cleared
+    if let Some(session) = token_from_header_or_cookie(header, cookie) {
+        let row = sqlx::query!("SELECT family_id FROM session WHERE session = ?", session)
+            .fetch_optional(&database.pool)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+
+        sqlx::query!("DELETE FROM session WHERE session = ?", session)
+            .execute(&database.pool)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+
+        // also revoke the refresh-token family this session was issued
+        // alongside, so a retained token can't mint a new session after
+        // logout. Scoped to this session's own family_id rather than every
+        // refresh token the user holds, so logging out on one device
+        // doesn't silently sign the user out everywhere else.
+        if let Some(row) = row {
+            sqlx::query!("DELETE FROM refresh_token WHERE family_id = ?", row.family_id)
+                .execute(&database.pool)
+                .await
+                .map_err(|e| warp::reject::custom(ApiError::from(e)))?;
+        }
+    }
+
+    Ok(warp::reply::with_header(
+        warp::reply(),
+        "Set-Cookie",
+        cleared_session_cookie(),
+    ))
 }
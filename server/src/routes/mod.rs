@@ -0,0 +1,31 @@
+pub mod handlers;
+
+use serde::{Deserialize, Serialize};
+use warp::reject::Reject;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LoginData {
+    pub username: String,
+    pub pw: String,
+    pub remember: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RefreshData {
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub enum ApiError {
+    NotAuthorized,
+    SessionExpired,
+    DatabaseError(String),
+}
+
+impl Reject for ApiError {}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        ApiError::DatabaseError(error.to_string())
+    }
+}
@@ -0,0 +1,119 @@
+use test_util::spawn_server;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize)]
+pub struct LoginData {
+    pub username: String,
+    pub pw: String,
+    pub remember: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RefreshData {
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponseData {
+    pub session: String,
+    pub refresh_token: String,
+}
+
+#[tokio::test]
+async fn test_logout_invalidates_session_and_refresh_token() {
+    let (server_task, address, cancel_token) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let map = LoginData {
+        username: "my_id".to_string(),
+        pw: "my_pw".to_string(),
+        remember: true,
+    };
+    let login_response: ResponseData = client
+        .post(format!("http://127.0.0.1:{}/auth/login", address.port()))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to send request.")
+        .json()
+        .await
+        .expect("Failed to parse login response.");
+
+    let logout_response = client
+        .post(format!("http://127.0.0.1:{}/auth/logout", address.port()))
+        .header("authorization", format!("Bearer {}", login_response.session))
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert!(logout_response.status().is_success());
+    assert!(
+        logout_response.headers().get("set-cookie").is_some(),
+        "logout should clear the session cookie"
+    );
+
+    // the refresh token issued at login should no longer work once the
+    // session it belongs to has been logged out
+    let refresh_after_logout = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: login_response.refresh_token })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert_eq!(refresh_after_logout.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    cancel_token.cancel();
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_logout_does_not_revoke_other_sessions_refresh_token() {
+    let (server_task, address, cancel_token) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let map = LoginData {
+        username: "my_id".to_string(),
+        pw: "my_pw".to_string(),
+        remember: true,
+    };
+
+    // two independent logins for the same user, e.g. a phone and a laptop
+    let first_login: ResponseData = client
+        .post(format!("http://127.0.0.1:{}/auth/login", address.port()))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to send request.")
+        .json()
+        .await
+        .expect("Failed to parse login response.");
+    let second_login: ResponseData = client
+        .post(format!("http://127.0.0.1:{}/auth/login", address.port()))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to send request.")
+        .json()
+        .await
+        .expect("Failed to parse login response.");
+
+    let logout_response = client
+        .post(format!("http://127.0.0.1:{}/auth/logout", address.port()))
+        .header("authorization", format!("Bearer {}", first_login.session))
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert!(logout_response.status().is_success());
+
+    // logging out the first session must not revoke the second session's
+    // refresh token family
+    let refresh_second = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: second_login.refresh_token })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert!(refresh_second.status().is_success());
+
+    cancel_token.cancel();
+    server_task.await.unwrap();
+}
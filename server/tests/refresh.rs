@@ -0,0 +1,149 @@
+use test_util::spawn_server;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize)]
+pub struct LoginData {
+    pub username: String,
+    pub pw: String,
+    pub remember: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RefreshData {
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponseData {
+    pub session: String,
+    pub refresh_token: String,
+}
+
+async fn login(client: &reqwest::Client, port: u16) -> ResponseData {
+    let map = LoginData {
+        username: "my_id".to_string(),
+        pw: "my_pw".to_string(),
+        remember: true,
+    };
+
+    client
+        .post(format!("http://127.0.0.1:{}/auth/login", port))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to send request.")
+        .json()
+        .await
+        .expect("Failed to parse login response.")
+}
+
+#[tokio::test]
+async fn test_refresh_rotates_token() {
+    let (server_task, address, cancel_token) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let login_response = login(&client, address.port()).await;
+
+    let response = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: login_response.refresh_token.clone() })
+        .send()
+        .await
+        .expect("Failed to send request.");
+
+    assert!(response.status().is_success());
+    let refreshed: ResponseData = response.json().await.expect("Failed to parse refresh response.");
+    assert_ne!(refreshed.refresh_token, login_response.refresh_token);
+    assert_ne!(refreshed.session, login_response.session);
+
+    cancel_token.cancel();
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_replayed_refresh_token_revokes_family() {
+    let (server_task, address, cancel_token) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let login_response = login(&client, address.port()).await;
+
+    // first redemption succeeds and rotates the token
+    let first = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: login_response.refresh_token.clone() })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert!(first.status().is_success());
+    let first: ResponseData = first.json().await.expect("Failed to parse refresh response.");
+
+    // replaying the original (already-rotated) token is a compromise: the
+    // whole family, including the token just minted above, gets revoked
+    let replay = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: login_response.refresh_token })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert_eq!(replay.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let revoked = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: first.refresh_token })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert_eq!(revoked.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    cancel_token.cancel();
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_refresh_only_one_wins_and_family_is_revoked() {
+    let (server_task, address, cancel_token) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let login_response = login(&client, address.port()).await;
+    let port = address.port();
+
+    let refresh = |token: String| {
+        let client = client.clone();
+        async move {
+            client
+                .post(format!("http://127.0.0.1:{}/auth/refresh", port))
+                .json(&RefreshData { refresh_token: token })
+                .send()
+                .await
+                .expect("Failed to send request.")
+        }
+    };
+
+    let (a, b) = tokio::join!(
+        refresh(login_response.refresh_token.clone()),
+        refresh(login_response.refresh_token.clone()),
+    );
+
+    let statuses = [a.status(), b.status()];
+    let successes = statuses.iter().filter(|s| s.is_success()).count();
+    assert_eq!(successes, 1, "exactly one concurrent redemption of the same token should succeed");
+
+    // the race is treated like a replay, so the family (including the
+    // winning redemption's brand new token) is revoked
+    let winner_body: ResponseData = if a.status().is_success() {
+        a.json().await.expect("Failed to parse refresh response.")
+    } else {
+        b.json().await.expect("Failed to parse refresh response.")
+    };
+
+    let follow_up = client
+        .post(format!("http://127.0.0.1:{}/auth/refresh", address.port()))
+        .json(&RefreshData { refresh_token: winner_body.refresh_token })
+        .send()
+        .await
+        .expect("Failed to send request.");
+    assert_eq!(follow_up.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    cancel_token.cancel();
+    server_task.await.unwrap();
+}